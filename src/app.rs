@@ -0,0 +1,68 @@
+/// How benchmarks within a comparison are ranked against one another, which
+/// in turn decides what `divide_by` and the coloring use as the denominator.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RankingConfig {
+    /// Normalize against whichever benchmark was fastest in the group.
+    Benchmark,
+    /// Normalize against whichever benchmark came first on the commandline.
+    Baseline,
+    /// Normalize against a specific, named benchmark in every group (e.g. a
+    /// `main`-branch baseline), regardless of speed or commandline order.
+    /// Groups that don't contain this name get no ratios or colors: the
+    /// terminal layouts mark them `NO REFERENCE BENCHMARK FOUND`, Markdown,
+    /// CSV and the `fail_over` gate skip them, and JSON lists them as usual.
+    Reference(String),
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ValueFormat {
+    Percent,
+    Real,
+}
+
+/// How `Comparisons::write` renders its output.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OutputFormat {
+    /// The colorized, tab-aligned layout meant for a terminal.
+    Terminal,
+    /// A GitHub-flavored Markdown table per group, suitable for PR comments.
+    Markdown,
+    /// One row per (group, benchmark), suitable for spreadsheets/dashboards.
+    Csv,
+    /// The full comparison tree, suitable for CI artifacts and tooling.
+    Json,
+}
+
+#[derive(Clone, Debug)]
+pub struct DisplayConfig {
+    pub list: bool,
+    pub value_format: ValueFormat,
+    pub rank: RankingConfig,
+    /// Number of standard deviations a change must clear, relative to the
+    /// propagated noise, before it is colored. Only applies in
+    /// `RankingConfig::Baseline` and `RankingConfig::Reference` modes, and
+    /// only when both benchmarks being compared have a stddev available.
+    pub significance: f64,
+    /// Render an extra column showing each benchmark's speed ratio against
+    /// the comparison's reference, e.g. `1.50 ±0.04×`.
+    pub relative: bool,
+    pub format: OutputFormat,
+    /// If set, `Comparisons::write` returns an error when any group's
+    /// biggest regression against its reference exceeds this percentage,
+    /// for use as a CI gate.
+    pub fail_over: Option<f64>,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> DisplayConfig {
+        DisplayConfig {
+            list: false,
+            value_format: ValueFormat::Percent,
+            rank: RankingConfig::Baseline,
+            significance: 2.0,
+            relative: false,
+            format: OutputFormat::Terminal,
+            fail_over: None,
+        }
+    }
+}
@@ -0,0 +1,44 @@
+/// The summarized timing (mean and stddev) of one benchmark, as read from a criterion
+/// `estimates.json`/`sample.json` pair on disk.
+#[derive(Clone, Debug)]
+pub struct Benchmark {
+    fullname: String,
+    nanoseconds: f64,
+    stddev: f64,
+    throughput: Option<Throughput>,
+}
+
+impl Benchmark {
+    pub fn new(
+        fullname: String,
+        nanoseconds: f64,
+        stddev: f64,
+        throughput: Option<Throughput>,
+    ) -> Benchmark {
+        Benchmark { fullname, nanoseconds, stddev, throughput }
+    }
+
+    /// The full name of this benchmark, in `<group>/<name>` form when it
+    /// belongs to a group.
+    pub fn fullname(&self) -> &str {
+        &self.fullname
+    }
+
+    pub fn nanoseconds(&self) -> f64 {
+        self.nanoseconds
+    }
+
+    pub fn stddev(&self) -> f64 {
+        self.stddev
+    }
+
+    pub fn throughput(&self) -> Option<Throughput> {
+        self.throughput
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Throughput {
+    Bytes(f64),
+    Elements(f64),
+}
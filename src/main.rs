@@ -0,0 +1,19 @@
+use std::error::Error;
+use std::process;
+
+mod app;
+mod data;
+mod output;
+
+pub type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<()> {
+    unimplemented!("criterion data loading and CLI parsing live outside this snapshot")
+}
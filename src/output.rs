@@ -4,7 +4,7 @@ use std::iter;
 use termcolor::{Color, ColorSpec, WriteColor};
 use unicode_width::UnicodeWidthStr;
 
-use crate::app::{DisplayConfig, RankingConfig, ValueFormat};
+use crate::app::{DisplayConfig, OutputFormat, RankingConfig, ValueFormat};
 use crate::data;
 use crate::Result;
 
@@ -54,12 +54,176 @@ impl Comparisons {
     }
 
     pub fn write(&self, mut wtr: Box<dyn WriteColor>) -> Result<()> {
-        if self.config.list {
-            self.rows(wtr.as_mut())?;
-        } else {
-            self.columns(wtr.as_mut())?;
+        match self.config.format {
+            OutputFormat::Terminal if self.config.list => {
+                self.rows(wtr.as_mut())?
+            }
+            OutputFormat::Terminal => self.columns(wtr.as_mut())?,
+            OutputFormat::Markdown => self.write_markdown(wtr.as_mut())?,
+            OutputFormat::Csv => self.write_csv(wtr.as_mut())?,
+            OutputFormat::Json => self.write_json(wtr.as_mut())?,
         }
         wtr.flush()?;
+        self.check_fail_over()
+    }
+
+    /// If `fail_over` is configured, report and fail when any group's worst
+    /// regression against its reference exceeds the configured percentage.
+    ///
+    /// The report is printed to stderr rather than through `write`'s `wtr`,
+    /// since `wtr` may be carrying a structured `OutputFormat::Json`/`Csv`
+    /// payload that a gate failure must not corrupt.
+    fn check_fail_over(&self) -> Result<()> {
+        let limit = match self.config.fail_over {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+
+        let offenders: Vec<(&str, &str, f64)> = self
+            .comps
+            .iter()
+            .filter_map(|comp| {
+                let reference = self.reference(comp)?;
+                let (name, pct) = comp.worst_regression(reference)?;
+                if pct > limit {
+                    Some((comp.name.as_str(), name, pct))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        if offenders.is_empty() {
+            return Ok(());
+        }
+
+        eprintln!();
+        eprintln!(
+            "regression gate failed: benchmarks exceeded the {:.1}% limit:",
+            limit,
+        );
+        for (group, name, pct) in &offenders {
+            eprintln!("  {}/{}: +{:.1}%", group, name, pct);
+        }
+        Err(format!(
+            "{} group(s) exceeded the {:.1}% regression limit",
+            offenders.len(),
+            limit,
+        )
+        .into())
+    }
+
+    fn write_markdown<W: WriteColor>(&self, mut wtr: W) -> Result<()> {
+        for comp in &self.comps {
+            if comp.benchmarks.is_empty() {
+                continue;
+            }
+            let reference = match self.reference(comp) {
+                Some(r) => r,
+                None => continue,
+            };
+
+            writeln!(wtr, "### {}", comp.name)?;
+            writeln!(wtr)?;
+            writeln!(wtr, "| benchmark | time | relative | throughput |")?;
+            writeln!(wtr, "|---|---|---|---|")?;
+            for name in &comp.cmdline_ordered {
+                let b = comp.get(name).unwrap();
+                writeln!(
+                    wtr,
+                    "| {} | {} | {} | {} |",
+                    b.name,
+                    time(b.nanoseconds, b.stddev),
+                    relative(b, reference),
+                    throughput(b.throughput),
+                )?;
+            }
+            writeln!(wtr)?;
+        }
+        Ok(())
+    }
+
+    fn write_csv<W: WriteColor>(&self, mut wtr: W) -> Result<()> {
+        writeln!(
+            wtr,
+            "group,benchmark,nanoseconds,stddev,throughput,throughput_unit,ratio"
+        )?;
+        for comp in &self.comps {
+            if comp.benchmarks.is_empty() {
+                continue;
+            }
+            // Matches `RankingConfig::Reference`'s documented skip semantics:
+            // a group missing the named reference contributes no rows.
+            let reference = match self.reference(comp) {
+                Some(r) => r,
+                None => continue,
+            };
+            for name in &comp.cmdline_ordered {
+                let b = comp.get(name).unwrap();
+                let ratio = format!("{:.4}", b.nanoseconds / reference.nanoseconds);
+                let (throughput, throughput_unit) = throughput_raw(b.throughput);
+                writeln!(
+                    wtr,
+                    "{},{},{},{},{},{},{}",
+                    csv_field(comp.group.as_deref().unwrap_or("")),
+                    csv_field(&b.name),
+                    b.nanoseconds,
+                    b.stddev.map(|s| s.to_string()).unwrap_or_default(),
+                    throughput,
+                    throughput_unit,
+                    ratio,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_json<W: WriteColor>(&self, mut wtr: W) -> Result<()> {
+        write!(wtr, "[")?;
+        for (i, comp) in self.comps.iter().enumerate() {
+            if i > 0 {
+                write!(wtr, ",")?;
+            }
+            write!(
+                wtr,
+                r#"{{"name":{},"group":{},"benchmarks":["#,
+                json_string(&comp.name),
+                comp.group
+                    .as_deref()
+                    .map(json_string)
+                    .unwrap_or_else(|| "null".to_string()),
+            )?;
+            for (j, name) in comp.cmdline_ordered.iter().enumerate() {
+                if j > 0 {
+                    write!(wtr, ",")?;
+                }
+                let b = comp.get(name).unwrap();
+                write!(
+                    wtr,
+                    r#"{{"name":{},"nanoseconds":{},"stddev":{},"throughput":{}}}"#,
+                    json_string(&b.name),
+                    b.nanoseconds,
+                    b.stddev
+                        .map(|s| s.to_string())
+                        .unwrap_or_else(|| "null".to_string()),
+                    b.throughput
+                        .map(json_throughput)
+                        .unwrap_or_else(|| "null".to_string()),
+                )?;
+            }
+            write!(
+                wtr,
+                r#"],"perf_ordered":["#,
+            )?;
+            for (j, name) in comp.perf_ordered.iter().enumerate() {
+                if j > 0 {
+                    write!(wtr, ",")?;
+                }
+                write!(wtr, "{}", json_string(name))?;
+            }
+            write!(wtr, "]}}")?;
+        }
+        write!(wtr, "]")?;
+        writeln!(wtr)?;
         Ok(())
     }
 
@@ -92,8 +256,19 @@ impl Comparisons {
                 continue;
             }
 
+            let reference = match self.reference(&comp) {
+                Some(r) => r,
+                None => {
+                    writeln!(
+                        wtr,
+                        "{}\tNO REFERENCE BENCHMARK FOUND",
+                        comp.name,
+                    )?;
+                    continue;
+                }
+            };
             write!(wtr, "{}", comp.name)?;
-            let divide_by = self.divide_by(&comp);
+            let divide_by = reference.nanoseconds;
             for column_name in &columns {
                 let b = match comp.get(column_name) {
                     Some(b) => b,
@@ -107,25 +282,19 @@ impl Comparisons {
                     ValueFormat::Percent => (val * 100.0, "%"),
                     ValueFormat::Real => (val, ""),
                 };
-                let color_set =
-                    self.set_color(&mut wtr, comp, b.nanoseconds)?;
+                let color_set = self.set_color(&mut wtr, comp, b)?;
+                write!(
+                    wtr,
+                    "\t  {:>8.2}{} {:>14}",
+                    val,
+                    sign,
+                    time(b.nanoseconds, b.stddev),
+                )?;
                 if throughput_available {
-                    write!(
-                        wtr,
-                        "\t  {:>8.2}{} {:>14} {:>14}",
-                        val,
-                        sign,
-                        time(b.nanoseconds, b.stddev),
-                        throughput(b.throughput),
-                    )?;
-                } else {
-                    write!(
-                        wtr,
-                        "\t  {:>8.2}{} {:>14}",
-                        val,
-                        sign,
-                        time(b.nanoseconds, b.stddev),
-                    )?;
+                    write!(wtr, " {:>14}", throughput(b.throughput))?;
+                }
+                if self.config.relative {
+                    write!(wtr, " {:>14}", relative(b, reference))?;
                 }
                 if color_set {
                     wtr.reset()?;
@@ -140,12 +309,12 @@ impl Comparisons {
         &self,
         wtr: &mut W,
         comp: &Comparison,
-        current: f64,
+        current: &Benchmark,
     ) -> Result<bool> {
         let color_conf = match self.config.rank {
-            RankingConfig::Baseline => {
-                self.set_colors_baseline_mode(comp, current)
-            }
+            RankingConfig::Baseline | RankingConfig::Reference(_) => self
+                .reference(comp)
+                .and_then(|r| self.set_colors_baseline_mode(r, current)),
             RankingConfig::Benchmark => {
                 self.set_colors_benchmark_mode(comp, current)
             }
@@ -164,10 +333,10 @@ impl Comparisons {
     fn set_colors_benchmark_mode(
         &self,
         comp: &Comparison,
-        current: f64,
+        current: &Benchmark,
     ) -> Option<(Color, bool)> {
         let best = comp.best().unwrap().nanoseconds;
-        if best == current {
+        if best == current.nanoseconds {
             Some((Color::Green, true))
         } else {
             None
@@ -176,14 +345,20 @@ impl Comparisons {
 
     fn set_colors_baseline_mode(
         &self,
-        comp: &Comparison,
-        current: f64,
+        ref_bench: &Benchmark,
+        current: &Benchmark,
     ) -> Option<(Color, bool)> {
         const THRESHOLD1: f64 = 0.03;
         const THRESHOLD2: f64 = 0.1;
-        let first = comp.first().unwrap().nanoseconds;
-        let val = current / first;
+        let val = current.nanoseconds / ref_bench.nanoseconds;
         let diff = val - 1.0;
+
+        if let Some(rel_sd) = relative_noise(current, ref_bench) {
+            if diff.abs() <= self.config.significance * rel_sd {
+                return None;
+            }
+        }
+
         if diff > 0.0 {
             if diff < THRESHOLD1 {
                 return None;
@@ -228,14 +403,20 @@ impl Comparisons {
             return Ok(());
         }
 
-        let divide_by = self.divide_by(comp);
+        let reference = match self.reference(comp) {
+            Some(r) => r,
+            None => {
+                writeln!(wtr, "NO REFERENCE BENCHMARK FOUND")?;
+                return Ok(());
+            }
+        };
         for b in comp.benchmarks.values() {
-            let val = b.nanoseconds / divide_by;
+            let val = b.nanoseconds / reference.nanoseconds;
             let (val, sign) = match self.config.value_format {
                 ValueFormat::Percent => (val * 100.0, "%"),
                 ValueFormat::Real => (val, ""),
             };
-            writeln!(
+            write!(
                 wtr,
                 "{}\t{:>7.2}{}\t{:>15}\t{:>12}",
                 b.name,
@@ -244,14 +425,25 @@ impl Comparisons {
                 time(b.nanoseconds, b.stddev),
                 throughput(b.throughput),
             )?;
+            if self.config.relative {
+                write!(wtr, "\t{:>14}", relative(b, reference))?;
+            }
+            writeln!(wtr, "")?;
         }
         Ok(())
     }
 
-    fn divide_by(&self, comp: &Comparison) -> f64 {
-        match self.config.rank {
-            RankingConfig::Benchmark => comp.best().unwrap().nanoseconds,
-            RankingConfig::Baseline => comp.first().unwrap().nanoseconds,
+    /// The benchmark that `divide_by`, coloring and the relative column are
+    /// all normalized against, per `RankingConfig`.
+    ///
+    /// Returns `None` for an empty comparison, and for
+    /// `RankingConfig::Reference` when the named benchmark isn't present in
+    /// this particular group.
+    fn reference<'c>(&self, comp: &'c Comparison) -> Option<&'c Benchmark> {
+        match &self.config.rank {
+            RankingConfig::Benchmark => comp.best(),
+            RankingConfig::Baseline => comp.first(),
+            RankingConfig::Reference(name) => comp.get(name),
         }
     }
 }
@@ -325,6 +517,18 @@ impl Comparison {
     fn get(&self, name: &str) -> Option<&Benchmark> {
         self.benchmarks.get(name)
     }
+
+    /// The name and percentage regression of whichever benchmark here is
+    /// farthest above `reference`, or `None` if this comparison is empty.
+    fn worst_regression(&self, reference: &Benchmark) -> Option<(&str, f64)> {
+        self.benchmarks
+            .values()
+            .map(|b| {
+                let pct = (b.nanoseconds / reference.nanoseconds - 1.0) * 100.0;
+                (b.name.as_str(), pct)
+            })
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+    }
 }
 
 impl Benchmark {
@@ -342,6 +546,81 @@ impl Benchmark {
     }
 }
 
+/// Propagated relative noise between two benchmarks' means, i.e.
+/// `sqrt((s_cur/m_cur)^2 + (s_ref/m_ref)^2)`.
+///
+/// Returns `None` when either side has no stddev; coloring then uses only the
+/// fixed relative thresholds, and `relative` omits the `±` part.
+fn relative_noise(current: &Benchmark, reference: &Benchmark) -> Option<f64> {
+    let s_cur = current.stddev?;
+    let s_ref = reference.stddev?;
+    let rel_cur = s_cur / current.nanoseconds;
+    let rel_ref = s_ref / reference.nanoseconds;
+    Some((rel_cur * rel_cur + rel_ref * rel_ref).sqrt())
+}
+
+/// Render `b`'s speed ratio against `reference` as e.g. `1.50 ±0.04×`,
+/// propagating stddev when both sides have one. The reference itself always
+/// renders as `1.00×`.
+fn relative(b: &Benchmark, reference: &Benchmark) -> String {
+    if b.name == reference.name {
+        return "1.00×".to_string();
+    }
+    let r = b.nanoseconds / reference.nanoseconds;
+    match relative_noise(b, reference) {
+        Some(rel_sd) => format!("{:.2} ±{:.2}×", r, r * rel_sd),
+        None => format!("{:.2}×", r),
+    }
+}
+
+/// Quote a CSV field if it contains a comma, quote, or newline.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                out.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn json_throughput(t: data::Throughput) -> String {
+    use data::Throughput::*;
+    match t {
+        Bytes(num) => format!(r#"{{"bytes":{}}}"#, num),
+        Elements(num) => format!(r#"{{"elements":{}}}"#, num),
+    }
+}
+
+/// The raw numeric throughput value and its unit kind (`"bytes"` or
+/// `"elements"`), for machine-readable output formats.
+fn throughput_raw(t: Option<data::Throughput>) -> (String, &'static str) {
+    use data::Throughput::*;
+    match t {
+        Some(Bytes(num)) => (num.to_string(), "bytes"),
+        Some(Elements(num)) => (num.to_string(), "elements"),
+        None => (String::new(), ""),
+    }
+}
+
 fn write_divider<W: WriteColor>(
     mut wtr: W,
     divider: char,
@@ -397,3 +676,201 @@ fn throughput_per(per: f64, unit: &str) -> String {
         format!("{:.1} G{}/sec", (per / (1 << 30) as f64), unit)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bench(name: &str, nanoseconds: f64, stddev: Option<f64>) -> Benchmark {
+        Benchmark {
+            name: name.to_string(),
+            nanoseconds,
+            stddev,
+            throughput: None,
+        }
+    }
+
+    #[test]
+    fn relative_noise_propagates_both_sides() {
+        let reference = bench("ref", 100.0, Some(5.0));
+        let current = bench("cur", 110.0, Some(4.0));
+        let rel_sd = relative_noise(&current, &reference).unwrap();
+        let expected =
+            ((4.0f64 / 110.0).powi(2) + (5.0f64 / 100.0).powi(2)).sqrt();
+        assert!((rel_sd - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn relative_noise_none_without_both_stddevs() {
+        let reference = bench("ref", 100.0, None);
+        let current = bench("cur", 110.0, Some(4.0));
+        assert!(relative_noise(&current, &reference).is_none());
+    }
+
+    #[test]
+    fn significance_suppresses_changes_within_noise() {
+        let comps = Comparisons::new(Vec::new(), DisplayConfig::default());
+        let reference = bench("ref", 100.0, Some(5.0));
+        // A 4% change is well within the ~7% propagated noise here.
+        let current = bench("cur", 104.0, Some(5.0));
+        assert!(
+            comps.set_colors_baseline_mode(&reference, &current).is_none()
+        );
+    }
+
+    #[test]
+    fn significance_colors_changes_beyond_noise() {
+        let comps = Comparisons::new(Vec::new(), DisplayConfig::default());
+        let reference = bench("ref", 100.0, Some(1.0));
+        // A 30% jump dwarfs the ~1.3% propagated noise here.
+        let current = bench("cur", 130.0, Some(1.0));
+        assert!(
+            comps.set_colors_baseline_mode(&reference, &current).is_some()
+        );
+    }
+
+    #[test]
+    fn significance_falls_back_to_fixed_thresholds_without_stddev() {
+        let comps = Comparisons::new(Vec::new(), DisplayConfig::default());
+        let reference = bench("ref", 100.0, None);
+        let current = bench("cur", 104.0, None);
+        // No stddev on either side: the old fixed 3%/10% thresholds apply,
+        // so a 4% change is colored (just not bold).
+        assert_eq!(
+            comps.set_colors_baseline_mode(&reference, &current),
+            Some((Color::Red, false))
+        );
+    }
+
+    #[test]
+    fn worst_regression_finds_the_largest_increase_over_reference() {
+        let comp = Comparison::new(
+            "group/bench",
+            vec![
+                bench("old", 100.0, None),
+                bench("mid", 110.0, None),
+                bench("new", 150.0, None),
+            ],
+        );
+        let reference = comp.get("old").unwrap();
+        let (name, pct) = comp.worst_regression(reference).unwrap();
+        assert_eq!(name, "new");
+        assert!((pct - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn fail_over_errors_when_a_group_exceeds_the_limit() {
+        let comp = Comparison::new(
+            "group/bench",
+            vec![bench("old", 100.0, None), bench("new", 150.0, None)],
+        );
+        let config =
+            DisplayConfig { fail_over: Some(25.0), ..Default::default() };
+        let comps = Comparisons::new(vec![comp], config);
+        assert!(comps.check_fail_over().is_err());
+    }
+
+    #[test]
+    fn fail_over_passes_when_every_group_is_within_the_limit() {
+        let comp = Comparison::new(
+            "group/bench",
+            vec![bench("old", 100.0, None), bench("new", 110.0, None)],
+        );
+        let config =
+            DisplayConfig { fail_over: Some(25.0), ..Default::default() };
+        let comps = Comparisons::new(vec![comp], config);
+        assert!(comps.check_fail_over().is_ok());
+    }
+
+    #[test]
+    fn fail_over_is_a_no_op_when_unconfigured() {
+        let comp = Comparison::new(
+            "group/bench",
+            vec![bench("old", 100.0, None), bench("new", 1000.0, None)],
+        );
+        let comps = Comparisons::new(vec![comp], DisplayConfig::default());
+        assert!(comps.check_fail_over().is_ok());
+    }
+
+    #[test]
+    fn csv_field_quotes_only_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("a\"b"), "\"a\"\"b\"");
+        assert_eq!(csv_field("a\nb"), "\"a\nb\"");
+    }
+
+    #[test]
+    fn json_string_escapes_quotes_backslashes_and_newlines() {
+        assert_eq!(json_string("a\"b\\c\nd"), "\"a\\\"b\\\\c\\nd\"");
+    }
+
+    #[test]
+    fn json_string_escapes_control_characters() {
+        assert_eq!(json_string("\r\t\u{1}"), "\"\\r\\t\\u0001\"");
+    }
+
+    fn render<F>(f: F) -> String
+    where
+        F: FnOnce(&mut termcolor::NoColor<Vec<u8>>) -> Result<()>,
+    {
+        let mut wtr = termcolor::NoColor::new(Vec::new());
+        f(&mut wtr).unwrap();
+        String::from_utf8(wtr.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn write_csv_skips_groups_missing_their_reference() {
+        let comp = Comparison::new("group/bench", vec![bench("a", 100.0, None)]);
+        let config = DisplayConfig {
+            rank: RankingConfig::Reference("missing".to_string()),
+            ..Default::default()
+        };
+        let comps = Comparisons::new(vec![comp], config);
+        let out = render(|wtr| comps.write_csv(wtr));
+        assert_eq!(
+            out,
+            "group,benchmark,nanoseconds,stddev,throughput,throughput_unit,ratio\n"
+        );
+    }
+
+    #[test]
+    fn write_csv_emits_a_row_per_benchmark() {
+        let comp = Comparison::new(
+            "group/bench",
+            vec![bench("a", 100.0, None), bench("b", 120.0, None)],
+        );
+        let comps = Comparisons::new(vec![comp], DisplayConfig::default());
+        let out = render(|wtr| comps.write_csv(wtr));
+        assert!(out.contains("group,a,100,,,,1.0000"));
+        assert!(out.contains("group,b,120,,,,1.2000"));
+    }
+
+    #[test]
+    fn write_markdown_renders_a_table_per_group() {
+        let comp = Comparison::new(
+            "group/bench",
+            vec![bench("a", 100.0, None), bench("b", 120.0, None)],
+        );
+        let comps = Comparisons::new(vec![comp], DisplayConfig::default());
+        let out = render(|wtr| comps.write_markdown(wtr));
+        assert!(out.contains("### bench"));
+        assert!(out.contains("| benchmark | time | relative | throughput |"));
+        assert!(out.contains("| a |"));
+        assert!(out.contains("| b |"));
+    }
+
+    #[test]
+    fn write_json_renders_the_full_tree() {
+        let comp = Comparison::new(
+            "group/bench",
+            vec![bench("a", 100.0, None), bench("b", 120.0, None)],
+        );
+        let comps = Comparisons::new(vec![comp], DisplayConfig::default());
+        let out = render(|wtr| comps.write_json(wtr));
+        assert!(out.starts_with('['));
+        assert!(out.contains(r#""name":"bench","group":"group""#));
+        assert!(out.contains(r#""name":"a""#));
+        assert!(out.contains(r#""perf_ordered":["a","b"]"#));
+    }
+}